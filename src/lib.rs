@@ -5,78 +5,345 @@
 use std::fs;
 use std::error::Error;
 use std::env;
+use std::io::IsTerminal;
+use std::io::Read;
+use std::ops::Range;
+
+mod regex;
+use regex::Regex;
+
+/// How `--color` should decide whether to highlight matches.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Color {
+    /// Colorize only when stdout is a terminal.
+    Auto,
+    /// Always colorize.
+    Always,
+    /// Never colorize.
+    Never,
+}
 
 /// A structure to keep the arguments passed to minigrep via the terminal
 pub struct Config {
-    /// Query to search for in the given file
+    /// Query to search for in the given files
     pub query: String,
-    /// The file to search
-    pub filename: String,
+    /// The files (or, with `recursive`, directories) to search
+    pub paths: Vec<String>,
+    /// Whether to walk directories in `paths` recursively, gathering regular files
+    pub recursive: bool,
+    /// Whether to read the search contents from standard input instead of `paths`,
+    /// because no filename was given or it was exactly `-`
+    pub use_stdin: bool,
     /// Whether the search should be case-sensitive or not
     pub case_sensitive: bool,
+    /// Whether matching lines should be inverted, i.e. only non-matching lines are printed
+    pub invert_match: bool,
+    /// Whether to prefix each printed line with its 1-based line number
+    pub line_number: bool,
+    /// Whether to print only the count of matching lines instead of the lines themselves
+    pub count: bool,
+    /// Whether `query` should be compiled and matched as a regular expression
+    pub regex: bool,
+    /// Whether matched text should be highlighted in the printed output
+    pub color: Color,
 }
 
 impl Config {
     /// Creates a new instance of Config using the passed arguments iterator.
-    /// The search is configured to be case-sensitive by default.
+    ///
+    /// Recognized flags are `-i`/`--ignore-case`, `-v`/`--invert-match`,
+    /// `-n`/`--line-number`, `-c`/`--count`, `-E`/`--regex`,
+    /// `-r`/`--recursive` and `--color=auto|always|never`. Flag parsing stops at a bare
+    /// `--`, after which every remaining argument is treated as positional
+    /// (so a query or filename starting with `-` can still be passed).
+    /// When `-i` isn't passed, case sensitivity falls back to the
+    /// `CASE_INSENSITIVE` environment variable, as before. If no filename is
+    /// given, or it is exactly `-`, the search contents are read from
+    /// standard input instead.
     ///
     /// # Examples:
-    /// 
+    ///
     /// ```
     /// // would produce an Err if required arguments not provided from terminal
     /// let config = minigrep::Config::new(std::env::args());
     /// ```
     pub fn new(mut args: std::env::Args) -> Result<Config, &'static str> {
         args.next();
-        let query = match args.next() {
+
+        let mut ignore_case = false;
+        let mut invert_match = false;
+        let mut line_number = false;
+        let mut count = false;
+        let mut regex = false;
+        let mut recursive = false;
+        let mut color = Color::Auto;
+        let mut positional = Vec::new();
+        let mut parsing_flags = true;
+
+        for arg in args {
+            if parsing_flags && arg == "--" {
+                parsing_flags = false;
+                continue;
+            }
+            if parsing_flags && arg.starts_with('-') && arg != "-" {
+                match arg.as_str() {
+                    "-i" | "--ignore-case" => ignore_case = true,
+                    "-v" | "--invert-match" => invert_match = true,
+                    "-n" | "--line-number" => line_number = true,
+                    "-c" | "--count" => count = true,
+                    "-E" | "--regex" => regex = true,
+                    "-r" | "--recursive" => recursive = true,
+                    "--color=auto" => color = Color::Auto,
+                    "--color=always" => color = Color::Always,
+                    "--color=never" => color = Color::Never,
+                    _ => return Err("Unrecognized flag"),
+                }
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let query = match positional.next() {
             Some(arg) => arg,
             None => return Err("Didn't get a query string")
         };
-        let filename = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a filename")
+        let paths: Vec<String> = positional.collect();
+        let use_stdin = paths.is_empty() || (paths.len() == 1 && paths[0] == "-");
+
+        let case_sensitive = if ignore_case {
+            false
+        } else {
+            env::var("CASE_INSENSITIVE").is_err()
         };
-        let case_sensitive = env::var("CASE_INSENSITIVE").is_err();
-        return Ok(Config{query, filename, case_sensitive});
+
+        return Ok(Config{query, paths, recursive, use_stdin, case_sensitive, invert_match, line_number, count, regex, color});
     }
 }
 
+/// A line that matched a query, along with where in the line the match was found.
+#[derive(Debug, PartialEq)]
+struct Match<'a> {
+    /// 1-based line number within the searched contents
+    line_number: usize,
+    /// The full text of the matching line
+    line: &'a str,
+    /// The byte range of the match within `line`
+    span: Range<usize>,
+}
+
 /// Run the program with the given Config instance
 ///
 /// # Examples:
-/// 
+///
 /// ```
-/// let (query, filename, case_sensitive) = (String::from("to"), String::from("poem.txt"), false);
-/// let config = minigrep::Config {query, filename, case_sensitive,};
+/// let config = minigrep::Config {
+///     query: String::from("to"),
+///     paths: vec![String::from("poem.txt")],
+///     recursive: false,
+///     use_stdin: false,
+///     case_sensitive: false,
+///     invert_match: false,
+///     line_number: false,
+///     count: false,
+///     regex: false,
+///     color: minigrep::Color::Auto,
+/// };
 /// minigrep::run(config);
 /// ```
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(config.filename)?;
-    let results = if config.case_sensitive {
-        search(&config.query, &contents)
-    } else {
-        search_case_insensitive(&config.query, &contents)
+    let use_color = match config.color {
+        Color::Always => true,
+        Color::Never => false,
+        Color::Auto => std::io::stdout().is_terminal(),
     };
 
-    for line in results {
-        println!("{}", line);
+    if config.use_stdin {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+        search_file(&config, &contents, None, use_color);
+        return Ok(());
+    }
+
+    let files = collect_files(&config.paths, config.recursive);
+    let multiple_files = files.len() > 1;
+
+    let mut errors = Vec::new();
+    for filename in &files {
+        let contents = match fs::read_to_string(filename) {
+            Ok(contents) => contents,
+            Err(err) => {
+                errors.push(format!("{}: {}", filename, err));
+                continue;
+            }
+        };
+
+        let prefix = if multiple_files { Some(filename.as_str()) } else { None };
+        search_file(&config, &contents, prefix, use_color);
     }
+
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into());
+    }
+
     Ok(())
 }
 
-fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    let results = contents.lines().filter(|line| {
-        line.contains(&query)
-    }).collect();
-    results
+fn search_file(config: &Config, contents: &str, prefix: Option<&str>, use_color: bool) {
+    let matches = if config.regex {
+        search_regex(&config.query, contents, config.case_sensitive)
+    } else if config.case_sensitive {
+        search(&config.query, contents)
+    } else {
+        search_case_insensitive(&config.query, contents)
+    };
+
+    if config.invert_match {
+        let matched_lines: std::collections::HashSet<usize> =
+            matches.iter().map(|m| m.line_number).collect();
+
+        let mut match_count = 0;
+        for (index, line) in contents.lines().enumerate() {
+            if matched_lines.contains(&(index + 1)) {
+                continue;
+            }
+            match_count += 1;
+            if !config.count {
+                print_line(prefix, index + 1, line, config.line_number);
+            }
+        }
+        if config.count {
+            print_count(prefix, match_count);
+        }
+        return;
+    }
+
+    if config.count {
+        print_count(prefix, matches.len());
+        return;
+    }
+
+    for m in matches {
+        if use_color {
+            print_highlighted(prefix, m, config.line_number);
+        } else {
+            print_line(prefix, m.line_number, m.line, config.line_number);
+        }
+    }
+}
+
+fn print_count(prefix: Option<&str>, count: usize) {
+    match prefix {
+        Some(filename) => println!("{}:{}", filename, count),
+        None => println!("{}", count),
+    }
+}
+
+fn print_line(prefix: Option<&str>, line_number: usize, line: &str, with_line_number: bool) {
+    match (prefix, with_line_number) {
+        (Some(filename), true) => println!("{}:{}:{}", filename, line_number, line),
+        (Some(filename), false) => println!("{}:{}", filename, line),
+        (None, true) => println!("{}:{}", line_number, line),
+        (None, false) => println!("{}", line),
+    }
+}
+
+fn print_highlighted(prefix: Option<&str>, m: Match, with_line_number: bool) {
+    let before = &m.line[..m.span.start];
+    let matched = &m.line[m.span.clone()];
+    let after = &m.line[m.span.end..];
+    let highlighted = format!("{}\x1b[01;31m{}\x1b[0m{}", before, matched, after);
+    print_line(prefix, m.line_number, &highlighted, with_line_number);
 }
 
-fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-    let query = query.to_lowercase();
-    let results = contents.lines().filter(|line| {
-        line.to_lowercase().contains(&query)
-    }).collect();
-    results
+/// Expands `paths` into a flat list of file paths to search. When `recursive`
+/// is set, directories are walked (via `fs::read_dir`) gathering every
+/// regular file underneath them; otherwise each path is taken as-is.
+fn collect_files(paths: &[String], recursive: bool) -> Vec<String> {
+    let mut files = Vec::new();
+    for path in paths {
+        if recursive {
+            collect_recursive(std::path::Path::new(path), &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+fn collect_recursive(path: &std::path::Path, files: &mut Vec<String>) {
+    if path.is_dir() {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            collect_recursive(&entry.path(), files);
+        }
+    } else {
+        files.push(path.to_string_lossy().into_owned());
+    }
+}
+
+fn search<'a>(query: &str, contents: &'a str) -> Vec<Match<'a>> {
+    contents.lines().enumerate().filter_map(|(index, line)| {
+        line.find(query).map(|start| Match {
+            line_number: index + 1,
+            line,
+            span: start..start + query.len(),
+        })
+    }).collect()
+}
+
+fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<Match<'a>> {
+    let query_chars: Vec<char> = query.chars().collect();
+    contents.lines().enumerate().filter_map(|(index, line)| {
+        find_case_insensitive(&query_chars, line).map(|span| Match {
+            line_number: index + 1,
+            line,
+            span,
+        })
+    }).collect()
+}
+
+/// Finds the byte range of the leftmost case-insensitive match of
+/// `query_chars` in `line`, comparing character-by-character so the
+/// returned offsets always land on the original line's char boundaries
+/// (unlike comparing lowercased strings, whose byte length can differ
+/// from the original for some Unicode characters).
+fn find_case_insensitive(query_chars: &[char], line: &str) -> Option<Range<usize>> {
+    if query_chars.is_empty() {
+        return Some(0..0);
+    }
+
+    let chars: Vec<char> = line.chars().collect();
+    if query_chars.len() > chars.len() {
+        return None;
+    }
+
+    let byte_offset = |char_index: usize| -> usize {
+        chars[..char_index].iter().map(|c| c.len_utf8()).sum()
+    };
+
+    for start in 0..=chars.len() - query_chars.len() {
+        let is_match = chars[start..start + query_chars.len()].iter().zip(query_chars)
+            .all(|(&c, &q)| c.to_lowercase().eq(q.to_lowercase()));
+        if is_match {
+            return Some(byte_offset(start)..byte_offset(start + query_chars.len()));
+        }
+    }
+    None
+}
+
+fn search_regex<'a>(pattern: &str, contents: &'a str, case_sensitive: bool) -> Vec<Match<'a>> {
+    let regex = Regex::compile(pattern, case_sensitive);
+    contents.lines().enumerate().filter_map(|(index, line)| {
+        regex.find(line).map(|span| Match {
+            line_number: index + 1,
+            line,
+            span,
+        })
+    }).collect()
 }
 
 #[cfg(test)]
@@ -93,7 +360,7 @@ mod tests {
         Duct tape.".replace("        ", "");
 
         assert_eq!(
-            vec!["safe, fast, productive."],
+            vec![Match { line_number: 2, line: "safe, fast, productive.", span: 15..19 }],
             search(&query, &contents)
         );
     }
@@ -108,8 +375,44 @@ mod tests {
         Trust me.".replace("        ", "");
 
         assert_eq!(
-            vec!["Rust:", "Trust me."],
+            vec![
+                Match { line_number: 1, line: "Rust:", span: 0..4 },
+                Match { line_number: 4, line: "Trust me.", span: 1..5 },
+            ],
             search_case_insensitive(&query, &contents)
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn case_insensitive_with_multibyte_case_folding() {
+        // 'İ' lowercases to a two-character sequence ("i" + combining dot
+        // above), so a byte offset taken from the lowercased string would
+        // land outside the original (shorter) line and panic on slicing.
+        let contents = "İxmatch";
+        assert_eq!(
+            vec![Match { line_number: 1, line: "İxmatch", span: 3..8 }],
+            search_case_insensitive("match", contents)
+        );
+    }
+
+    #[test]
+    fn regex_search() {
+        let contents = "\
+        Rust:
+        safe, fast, productive.
+        Pick three.
+        Duct tape.".replace("        ", "");
+
+        assert_eq!(
+            vec![
+                Match { line_number: 2, line: "safe, fast, productive.", span: 15..19 },
+                Match { line_number: 4, line: "Duct tape.", span: 0..4 },
+            ],
+            search_regex("[Dd]uct", &contents, true)
+        );
+        assert_eq!(
+            vec![Match { line_number: 1, line: "Rust:", span: 0..4 }],
+            search_regex("^rust", &contents, false)
+        );
+    }
+}
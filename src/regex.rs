@@ -0,0 +1,206 @@
+//! A tiny backtracking regex matcher.
+//!
+//! This intentionally only supports the handful of constructs `minigrep`
+//! needs (`.`, `*`, `^`/`$` anchors and `[...]` character classes) so that
+//! `--regex` doesn't require pulling in a full regex engine as a dependency.
+
+/// A single element a [`Token`] can match against one character of input.
+#[derive(Debug, Clone, PartialEq)]
+enum Atom {
+    Char(char),
+    Any,
+    Class { chars: Vec<char>, ranges: Vec<(char, char)>, negated: bool },
+}
+
+impl Atom {
+    fn matches(&self, c: char, case_sensitive: bool) -> bool {
+        let eq = |a: char, b: char| if case_sensitive { a == b } else { a.eq_ignore_ascii_case(&b) };
+        match self {
+            Atom::Char(expected) => eq(*expected, c),
+            Atom::Any => true,
+            Atom::Class { chars, ranges, negated } => {
+                let hit = chars.iter().any(|&ch| eq(ch, c))
+                    || ranges.iter().any(|&(lo, hi)| {
+                        if case_sensitive {
+                            lo <= c && c <= hi
+                        } else {
+                            let c = c.to_ascii_lowercase();
+                            lo.to_ascii_lowercase() <= c && c <= hi.to_ascii_lowercase()
+                        }
+                    });
+                hit != *negated
+            }
+        }
+    }
+}
+
+/// One compiled pattern element, optionally repeated zero-or-more times via `*`.
+#[derive(Debug, Clone)]
+struct Token {
+    atom: Atom,
+    star: bool,
+}
+
+/// A compiled pattern, ready to be matched against lines of text.
+pub struct Regex {
+    tokens: Vec<Token>,
+    anchored_start: bool,
+    anchored_end: bool,
+    case_sensitive: bool,
+}
+
+impl Regex {
+    /// Compiles `pattern` into a [`Regex`].
+    pub fn compile(pattern: &str, case_sensitive: bool) -> Regex {
+        let mut chars = pattern.chars().peekable();
+        let anchored_start = chars.peek() == Some(&'^');
+        if anchored_start {
+            chars.next();
+        }
+
+        let mut body: Vec<char> = chars.collect();
+        let anchored_end = body.last() == Some(&'$');
+        if anchored_end {
+            body.pop();
+        }
+
+        let mut tokens = Vec::new();
+        let mut iter = body.into_iter().peekable();
+        while let Some(c) = iter.next() {
+            let atom = match c {
+                '.' => Atom::Any,
+                '[' => {
+                    let mut negated = false;
+                    if iter.peek() == Some(&'^') {
+                        negated = true;
+                        iter.next();
+                    }
+                    let mut chars = Vec::new();
+                    let mut ranges = Vec::new();
+                    while let Some(&next) = iter.peek() {
+                        if next == ']' {
+                            iter.next();
+                            break;
+                        }
+                        let lo = iter.next().unwrap();
+                        if iter.peek() == Some(&'-') {
+                            let mut lookahead = iter.clone();
+                            lookahead.next();
+                            if lookahead.peek().filter(|&&hi| hi != ']').is_some() {
+                                iter.next();
+                                let hi = iter.next().unwrap();
+                                ranges.push((lo, hi));
+                                continue;
+                            }
+                        }
+                        chars.push(lo);
+                    }
+                    Atom::Class { chars, ranges, negated }
+                }
+                other => Atom::Char(other),
+            };
+            let star = iter.peek() == Some(&'*');
+            if star {
+                iter.next();
+            }
+            tokens.push(Token { atom, star });
+        }
+
+        Regex { tokens, anchored_start, anchored_end, case_sensitive }
+    }
+
+    /// Returns the byte range of the leftmost match in `line`, if any.
+    pub fn find(&self, line: &str) -> Option<std::ops::Range<usize>> {
+        let chars: Vec<char> = line.chars().collect();
+        let byte_offset = |char_index: usize| -> usize {
+            chars[..char_index].iter().map(|c| c.len_utf8()).sum()
+        };
+
+        let last_start = if self.anchored_start { 0 } else { chars.len() };
+        for start in 0..=last_start {
+            if let Some(len) = self.match_here(&self.tokens, &chars[start..]) {
+                let start_byte = byte_offset(start);
+                let end_byte = byte_offset(start + len);
+                return Some(start_byte..end_byte);
+            }
+        }
+        None
+    }
+
+    fn match_here(&self, tokens: &[Token], text: &[char]) -> Option<usize> {
+        match tokens.split_first() {
+            None => {
+                if self.anchored_end && !text.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                }
+            }
+            Some((head, rest)) => {
+                if head.star {
+                    self.match_star(head, rest, text)
+                } else if !text.is_empty() && head.atom.matches(text[0], self.case_sensitive) {
+                    self.match_here(rest, &text[1..]).map(|n| n + 1)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn match_star(&self, head: &Token, rest: &[Token], text: &[char]) -> Option<usize> {
+        // Try zero occurrences first, then progressively more.
+        if let Some(n) = self.match_here(rest, text) {
+            return Some(n);
+        }
+        let mut consumed = 0;
+        while consumed < text.len() && head.atom.matches(text[consumed], self.case_sensitive) {
+            consumed += 1;
+            if let Some(n) = self.match_here(rest, &text[consumed..]) {
+                return Some(consumed + n);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_match() {
+        let re = Regex::compile("duct", true);
+        assert_eq!(re.find("safe, fast, productive."), Some(15..19));
+        assert_eq!(re.find("Pick three."), None);
+    }
+
+    #[test]
+    fn dot_and_star() {
+        let re = Regex::compile("f.*t", true);
+        assert_eq!(re.find("safe, fast, productive."), Some(2..10));
+    }
+
+    #[test]
+    fn anchors() {
+        let re = Regex::compile("^Rust", true);
+        assert_eq!(re.find("Rust:"), Some(0..4));
+        assert_eq!(re.find("I love Rust"), None);
+
+        let re = Regex::compile("tape.$", true);
+        assert_eq!(re.find("Duct tape."), Some(5..10));
+    }
+
+    #[test]
+    fn character_class() {
+        let re = Regex::compile("[A-Z]ust", true);
+        assert_eq!(re.find("Rust:"), Some(0..4));
+        assert_eq!(re.find("trust"), None);
+    }
+
+    #[test]
+    fn case_insensitive_match() {
+        let re = Regex::compile("rust", false);
+        assert_eq!(re.find("Trust me."), Some(1..5));
+    }
+}